@@ -1,6 +1,6 @@
 use axum::{
     body::Body,
-    extract::{Path, Query},
+    extract::{Path, Query, State},
     http::{header, HeaderMap, HeaderValue, Method, StatusCode},
     response::IntoResponse,
     routing::{get, post},
@@ -10,17 +10,116 @@ use base64::engine::general_purpose::STANDARD as Base64Engine;
 use base64::Engine;
 use bytes::Bytes;
 use dotenv::dotenv;
-use reqwest::header::{AUTHORIZATION, CONTENT_TYPE};
+use reqwest::header::{AUTHORIZATION, CONTENT_TYPE, RETRY_AFTER};
+use reqwest::StatusCode as ReqwestStatusCode;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use std::{collections::HashMap, env, net::SocketAddr};
+use std::{env, net::SocketAddr, sync::Arc, time::{Duration, Instant}};
 use thiserror::Error;
-use tokio::{io::AsyncReadExt, process::Command as AsyncCommand, sync::mpsc};
+use tokio::{io::AsyncReadExt, process::Command as AsyncCommand, sync::mpsc, sync::RwLock};
 use tokio_stream::wrappers::ReceiverStream;
 use tower_http::cors::{Any, CorsLayer};
 use tracing::{info};
 use tracing_subscriber;
 
+mod spotify_id;
+use spotify_id::SpotifyId;
+
+/// Margin subtracted from a token's `expires_in` so we refresh a little
+/// before Spotify actually invalidates it.
+const SPOTIFY_TOKEN_MARGIN_SECS: u64 = 30;
+
+/// How many times to retry a Spotify request after a `429` before giving up.
+const SPOTIFY_MAX_RETRIES: u32 = 3;
+
+/// `Retry-After` fallback when Spotify omits the header on a `429`.
+const SPOTIFY_DEFAULT_RETRY_AFTER_SECS: u64 = 5;
+
+/// Spotify's search endpoint caps `limit` at 50 per request.
+const SPOTIFY_SEARCH_CHUNK_SIZE: u32 = 50;
+
+/// `max_results` used by `/spotify/search` when the caller doesn't specify one.
+const SPOTIFY_DEFAULT_MAX_RESULTS: u32 = 10;
+
+/// Upper bound on `max_results`/`offset` so a client can't force the
+/// pagination loop into thousands of sequential requests against Spotify
+/// using the server's own app credentials.
+const SPOTIFY_MAX_RESULTS_CEILING: u32 = 200;
+const SPOTIFY_MAX_OFFSET: u32 = 1000;
+
+/// Sends a Spotify API request, retrying on `429 Too Many Requests` per the
+/// `Retry-After` header, and turning any other non-2xx response into a
+/// `SpotifyError` carrying the status and response body instead of silently
+/// parsing an empty/garbage JSON body.
+async fn send_spotify_request(req: &reqwest::RequestBuilder) -> Result<Value, AudioStreamError> {
+    let mut attempts = 0;
+
+    loop {
+        let attempt = req
+            .try_clone()
+            .ok_or(AudioStreamError::InternalError)?;
+
+        let res = attempt
+            .send()
+            .await
+            .map_err(|e| AudioStreamError::SpotifyError(e.to_string()))?;
+
+        let status = res.status();
+
+        if status.is_success() {
+            return res
+                .json::<Value>()
+                .await
+                .map_err(|e| AudioStreamError::SpotifyError(e.to_string()));
+        }
+
+        if status == ReqwestStatusCode::TOO_MANY_REQUESTS && attempts < SPOTIFY_MAX_RETRIES {
+            let retry_after = res
+                .headers()
+                .get(RETRY_AFTER)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.parse::<u64>().ok())
+                .unwrap_or(SPOTIFY_DEFAULT_RETRY_AFTER_SECS);
+
+            info!(
+                "Spotify rate limit hit, retrying in {}s (attempt {}/{})",
+                retry_after,
+                attempts + 1,
+                SPOTIFY_MAX_RETRIES
+            );
+            tokio::time::sleep(Duration::from_secs(retry_after)).await;
+            attempts += 1;
+            continue;
+        }
+
+        let body = res.text().await.unwrap_or_default();
+        return Err(AudioStreamError::SpotifyError(format!(
+            "{}: {}",
+            status, body
+        )));
+    }
+}
+
+/// ======= SPOTIFY TOKEN CACHE =======
+#[derive(Clone)]
+struct CachedToken {
+    access_token: String,
+    expires_at: Instant,
+}
+
+#[derive(Clone)]
+struct AppState {
+    spotify_token: Arc<RwLock<Option<CachedToken>>>,
+}
+
+impl AppState {
+    fn new() -> Self {
+        Self {
+            spotify_token: Arc::new(RwLock::new(None)),
+        }
+    }
+}
+
 /// ======= ERROR HANDLING =======
 #[derive(Debug, Error)]
 pub enum AudioStreamError {
@@ -38,6 +137,12 @@ pub enum AudioStreamError {
 
     #[error("Missing environment variable: {0}")]
     EnvVarError(String),
+
+    #[error("Unsupported audio format or bitrate: {0}")]
+    InvalidFormat(String),
+
+    #[error("Invalid Spotify ID or URL: {0}")]
+    InvalidSpotifyId(String),
 }
 
 impl IntoResponse for AudioStreamError {
@@ -48,6 +153,8 @@ impl IntoResponse for AudioStreamError {
             AudioStreamError::InternalError => StatusCode::INTERNAL_SERVER_ERROR,
             AudioStreamError::SpotifyError(_) => StatusCode::BAD_GATEWAY,
             AudioStreamError::EnvVarError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            AudioStreamError::InvalidFormat(_) => StatusCode::BAD_REQUEST,
+            AudioStreamError::InvalidSpotifyId(_) => StatusCode::BAD_REQUEST,
         };
 
         #[derive(Serialize)]
@@ -59,22 +166,93 @@ impl IntoResponse for AudioStreamError {
     }
 }
 
+/// Parses an open-ended `Range: bytes=<start>-` header into its start
+/// offset. Multi-range and closed-end requests fall back to just honoring
+/// the start, which is all `stream_youtube` can support against a
+/// transcoded stream of unknown length.
+fn parse_range_start(value: &str) -> Option<u64> {
+    let ranges = value.strip_prefix("bytes=")?;
+    let first = ranges.split(',').next()?;
+    let start = first.split('-').next()?;
+    start.trim().parse::<u64>().ok()
+}
+
+/// Audio formats `stream_youtube` will pass through to yt-dlp's
+/// `--audio-format`, paired with the `Content-Type` to advertise for each.
+const ALLOWED_AUDIO_FORMATS: &[(&str, &str)] = &[
+    ("mp3", "audio/mpeg"),
+    ("opus", "audio/ogg"),
+    ("m4a", "audio/mp4"),
+];
+
+/// Bitrates accepted for yt-dlp's `--audio-quality` (kbps).
+const ALLOWED_BITRATES: &[&str] = &["64", "96", "128", "160", "192", "256", "320"];
+
+#[derive(Deserialize)]
+struct StreamQuery {
+    format: Option<String>,
+    bitrate: Option<String>,
+}
+
+/// Validates the requested format (default `mp3`) against the allow-list
+/// above, returning the yt-dlp format string and its `Content-Type`.
+fn resolve_audio_format(format: Option<&str>) -> Result<(&'static str, &'static str), AudioStreamError> {
+    let format = format.unwrap_or("mp3");
+    ALLOWED_AUDIO_FORMATS
+        .iter()
+        .find(|(name, _)| *name == format)
+        .map(|(name, content_type)| (*name, *content_type))
+        .ok_or_else(|| AudioStreamError::InvalidFormat(format!("unsupported format `{}`", format)))
+}
+
+fn resolve_bitrate(bitrate: Option<&str>) -> Result<Option<&'static str>, AudioStreamError> {
+    match bitrate {
+        None => Ok(None),
+        Some(bitrate) => ALLOWED_BITRATES
+            .iter()
+            .find(|&&allowed| allowed == bitrate)
+            .map(|&allowed| Some(allowed))
+            .ok_or_else(|| AudioStreamError::InvalidFormat(format!("unsupported bitrate `{}`", bitrate))),
+    }
+}
+
 /// ======= STREAM YOUTUBE AUDIO =======
-async fn stream_youtube(Path(youtube_id): Path<String>)
-    -> Result<impl IntoResponse, AudioStreamError>
-{
+async fn stream_youtube(
+    Path(youtube_id): Path<String>,
+    Query(query): Query<StreamQuery>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, AudioStreamError> {
     if youtube_id.len() != 11 {
         return Err(AudioStreamError::InvalidYouTubeId);
     }
 
-    info!("Requested YouTube audio stream: {}", youtube_id);
+    let (format, content_type) = resolve_audio_format(query.format.as_deref())?;
+    let bitrate = resolve_bitrate(query.bitrate.as_deref())?;
+
+    let range_start = headers
+        .get(header::RANGE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(parse_range_start);
+
+    info!("Requested YouTube audio stream: {} (format: {}, bitrate: {:?}, range start: {:?})", youtube_id, format, bitrate, range_start);
     let url = format!("https://www.youtube.com/watch?v={}", youtube_id);
 
     let (tx, rx) = mpsc::channel::<Result<Bytes, std::io::Error>>(32);
 
     tokio::spawn(async move {
+        let mut args = vec!["-x".to_string(), "--audio-format".to_string(), format.to_string()];
+        if let Some(bitrate) = bitrate {
+            args.push("--audio-quality".to_string());
+            // ffmpeg/yt-dlp treat a bare number as a 0-10 VBR quality scale;
+            // the `k` suffix is what selects an actual kbps bitrate.
+            args.push(format!("{}k", bitrate));
+        }
+        args.push("-o".to_string());
+        args.push("-".to_string());
+        args.push(url.clone());
+
         let child_result = AsyncCommand::new("yt-dlp")
-            .args(&["-x", "--audio-format", "mp3", "-o", "-", &url])
+            .args(&args)
             .stdout(std::process::Stdio::piped())
             .stderr(std::process::Stdio::null())
             .spawn();
@@ -84,12 +262,25 @@ async fn stream_youtube(Path(youtube_id): Path<String>)
                 if let Some(stdout) = child.stdout.take() {
                     let mut reader = tokio::io::BufReader::new(stdout);
                     let mut buffer = [0u8; 8192];
+                    let mut to_skip = range_start.unwrap_or(0);
 
                     loop {
                         match reader.read(&mut buffer).await {
                             Ok(0) => break,
                             Ok(n) => {
-                                if tx.send(Ok(Bytes::copy_from_slice(&buffer[..n]))).await.is_err() {
+                                let mut chunk = &buffer[..n];
+
+                                if to_skip > 0 {
+                                    let skip_now = to_skip.min(n as u64) as usize;
+                                    chunk = &chunk[skip_now..];
+                                    to_skip -= skip_now as u64;
+                                }
+
+                                if chunk.is_empty() {
+                                    continue;
+                                }
+
+                                if tx.send(Ok(Bytes::copy_from_slice(chunk))).await.is_err() {
                                     break;
                                 }
                             }
@@ -110,11 +301,23 @@ async fn stream_youtube(Path(youtube_id): Path<String>)
 
     let stream = ReceiverStream::new(rx);
 
-    let mut headers = HeaderMap::new();
-    headers.insert(header::CONTENT_TYPE, HeaderValue::from_static("audio/mpeg"));
-    headers.insert(header::CACHE_CONTROL, HeaderValue::from_static("no-cache"));
+    let mut out_headers = HeaderMap::new();
+    out_headers.insert(header::CONTENT_TYPE, HeaderValue::from_static(content_type));
+    out_headers.insert(header::CACHE_CONTROL, HeaderValue::from_static("no-cache"));
+    out_headers.insert(header::ACCEPT_RANGES, HeaderValue::from_static("bytes"));
+
+    // The transcoded stream's total length is never known up front, so there
+    // is no valid `last-byte-pos`/`complete-length` to put in a `Content-Range`
+    // header (RFC 7233 has no syntax for an open-ended byte-range-resp). We
+    // still honor the request by skipping to `start` and replying `206`, just
+    // without a `Content-Range` header.
+    let status = if range_start.is_some() {
+        StatusCode::PARTIAL_CONTENT
+    } else {
+        StatusCode::OK
+    };
 
-    Ok((headers, Body::from_stream(stream)))
+    Ok((status, out_headers, Body::from_stream(stream)))
 }
 
 /// ======= YOUTUBE SEARCH =======
@@ -136,6 +339,14 @@ async fn yt_search(Query(params): Query<YtQuery>) -> Result<Json<YtResponse>, Au
         params.title.clone()
     };
 
+    let id = find_youtube_id(&search_query).await?;
+
+    Ok(Json(YtResponse { youtubeId: id }))
+}
+
+/// Runs a `ytsearch1:` lookup through yt-dlp and returns the top result's
+/// video ID. Shared by `/yt/search` and the Spotify URL resolver.
+async fn find_youtube_id(search_query: &str) -> Result<String, AudioStreamError> {
     let output = AsyncCommand::new("yt-dlp")
         .args(&["--get-id", &format!("ytsearch1:{}", search_query)])
         .output()
@@ -153,7 +364,7 @@ async fn yt_search(Query(params): Query<YtQuery>) -> Result<Json<YtResponse>, Au
 
     info!("YouTube search for '{}' returned ID: {}", search_query, id);
 
-    Ok(Json(YtResponse { youtubeId: id }))
+    Ok(id)
 }
 
 /// ======= SPOTIFY STRUCT ========
@@ -165,15 +376,32 @@ struct SpotifyTrack {
     artwork: String,
 }
 
+#[derive(Deserialize)]
+struct SpotifySearchRequest {
+    query: String,
+    #[serde(default)]
+    max_results: Option<u32>,
+    #[serde(default)]
+    offset: Option<u32>,
+}
+
 /// ======= SPOTIFY SEARCH ========
+/// Accepts either free-text search terms or a Spotify URI/URL in `query`;
+/// the latter is resolved directly through [`SpotifyId`] instead of hitting
+/// the search endpoint. ID detection only triggers on `spotify:` URIs and
+/// `open.spotify.com` URLs (see [`spotify_id::looks_like_spotify_reference`])
+/// — a bare 22-character string is treated as search text, since that's a
+/// plausible band/track name and not necessarily a pasted ID.
 async fn spotify_search(
-    Json(body): Json<HashMap<String, String>>,
+    State(state): State<AppState>,
+    Json(body): Json<SpotifySearchRequest>,
 ) -> Result<Json<Vec<SpotifyTrack>>, AudioStreamError> {
     dotenv().ok();
 
-    let q = body.get("query")
-        .ok_or_else(|| AudioStreamError::SpotifyError("Missing query".into()))?
-        .to_string();
+    let q = body.query.trim();
+    if q.is_empty() {
+        return Err(AudioStreamError::SpotifyError("Missing query".into()));
+    }
 
     let id = env::var("SPOTIFY_CLIENT_ID")
         .map_err(|_| AudioStreamError::EnvVarError("SPOTIFY_CLIENT_ID".into()))?;
@@ -181,61 +409,145 @@ async fn spotify_search(
     let secret = env::var("SPOTIFY_CLIENT_SECRET")
         .map_err(|_| AudioStreamError::EnvVarError("SPOTIFY_CLIENT_SECRET".into()))?;
 
-    let token = get_spotify_token(&id, &secret)
-        .await
-        .map_err(|e| AudioStreamError::SpotifyError(e.to_string()))?;
+    let token = get_cached_spotify_token(&state, &id, &secret).await?;
 
-    let tracks = search_spotify_tracks(&token, &q)
-        .await
-        .map_err(|e| AudioStreamError::SpotifyError(e.to_string()))?;
+    let parsed_id = if spotify_id::looks_like_spotify_reference(q) {
+        Some(q.parse::<SpotifyId>())
+    } else {
+        None
+    };
+
+    let tracks = match parsed_id {
+        Some(Ok(SpotifyId::Track(track_id))) => vec![get_spotify_track(&token, &track_id).await?],
+        Some(Ok(SpotifyId::Album(album_id))) => {
+            get_spotify_album_tracks(&token, &album_id).await?
+        }
+        Some(Ok(SpotifyId::Playlist(playlist_id))) => {
+            get_spotify_playlist_tracks(&token, &playlist_id).await?
+        }
+        Some(Ok(SpotifyId::Artist(_))) => {
+            return Err(AudioStreamError::InvalidSpotifyId(
+                "Artist lookups are not supported by search".into(),
+            ))
+        }
+        Some(Err(e)) => return Err(AudioStreamError::InvalidSpotifyId(e.to_string())),
+        None => {
+            let max_results = body
+                .max_results
+                .unwrap_or(SPOTIFY_DEFAULT_MAX_RESULTS)
+                .min(SPOTIFY_MAX_RESULTS_CEILING);
+            let offset = body.offset.unwrap_or(0).min(SPOTIFY_MAX_OFFSET);
+
+            search_spotify_tracks(&token, q, max_results, offset).await?
+        }
+    };
 
     Ok(Json(tracks))
 }
 
 /// ======= SPOTIFY TOKEN ========
+/// Returns a cached access token if one is still valid, otherwise fetches a
+/// fresh one from Spotify and stores it for subsequent callers.
+async fn get_cached_spotify_token(state: &AppState, id: &str, secret: &str)
+    -> Result<String, AudioStreamError>
+{
+    {
+        let cached = state.spotify_token.read().await;
+        if let Some(token) = cached.as_ref() {
+            if Instant::now() < token.expires_at {
+                return Ok(token.access_token.clone());
+            }
+        }
+    }
+
+    let (access_token, expires_in) = get_spotify_token(id, secret).await?;
+    let expires_at = Instant::now()
+        + Duration::from_secs(expires_in.saturating_sub(SPOTIFY_TOKEN_MARGIN_SECS));
+
+    let mut cached = state.spotify_token.write().await;
+    *cached = Some(CachedToken {
+        access_token: access_token.clone(),
+        expires_at,
+    });
+
+    Ok(access_token)
+}
+
+/// Fetches a fresh client-credentials token from Spotify, returning the
+/// access token alongside its `expires_in` (seconds).
 async fn get_spotify_token(id: &str, secret: &str)
-    -> Result<String, Box<dyn std::error::Error>>
+    -> Result<(String, u64), AudioStreamError>
 {
     let client = reqwest::Client::new();
     let creds = Base64Engine.encode(format!("{id}:{secret}"));
 
-    let res = client
+    let req = client
         .post("https://accounts.spotify.com/api/token")
         .header(CONTENT_TYPE, "application/x-www-form-urlencoded")
         .header(AUTHORIZATION, format!("Basic {}", creds))
-        .form(&[("grant_type", "client_credentials")])
-        .send()
-        .await?;
+        .form(&[("grant_type", "client_credentials")]);
 
-    let json: Value = res.json().await?;
-    Ok(json["access_token"].as_str().unwrap().to_string())
+    let json = send_spotify_request(&req).await?;
+    let access_token = json["access_token"].as_str().unwrap_or_default().to_string();
+    let expires_in = json["expires_in"].as_u64().unwrap_or(3600);
+    Ok((access_token, expires_in))
 }
 
 /// ======= SPOTIFY TRACK SEARCH ========
+/// Fetches up to `max_results` tracks starting at `offset`, paging through
+/// Spotify's search endpoint in `SPOTIFY_SEARCH_CHUNK_SIZE`-sized chunks
+/// (Spotify caps `limit` at 50) until that many results are collected or a
+/// page comes back short.
 async fn search_spotify_tracks(
     token: &str,
     query: &str,
-) -> Result<Vec<SpotifyTrack>, Box<dyn std::error::Error>> {
-    let url = format!(
-        "https://api.spotify.com/v1/search?q={}&type=track&limit=10",
-        urlencoding::encode(query)
-    );
+    max_results: u32,
+    offset: u32,
+) -> Result<Vec<SpotifyTrack>, AudioStreamError> {
+    let mut results = Vec::new();
+    let mut offset = offset;
+
+    while results.len() < max_results as usize {
+        let chunk_limit = (max_results as usize - results.len())
+            .min(SPOTIFY_SEARCH_CHUNK_SIZE as usize) as u32;
+
+        let url = format!(
+            "https://api.spotify.com/v1/search?q={}&type=track&limit={}&offset={}",
+            urlencoding::encode(query),
+            chunk_limit,
+            offset
+        );
+
+        let req = reqwest::Client::new()
+            .get(url)
+            .header(AUTHORIZATION, format!("Bearer {}", token));
+
+        let json = send_spotify_request(&req).await?;
+        let items = json["tracks"]["items"]
+            .as_array()
+            .map(|arr| arr.to_owned())
+            .unwrap_or_default();
+
+        if items.is_empty() {
+            break;
+        }
 
-    let res = reqwest::Client::new()
-        .get(url)
-        .header(AUTHORIZATION, format!("Bearer {}", token))
-        .send()
-        .await?;
-
-    let json: Value = res.json().await?;
- let items = json["tracks"]["items"]
-    .as_array()
-    .map(|arr| arr.to_owned())  // clone des données pour éviter la référence temporaire
-    .unwrap_or_default();
-
-let results = items
-    .iter()
-    .map(|i| SpotifyTrack {
+        let chunk_len = items.len();
+        results.extend(items.iter().map(spotify_track_from_json));
+        offset += chunk_len as u32;
+
+        if chunk_len < chunk_limit as usize {
+            break;
+        }
+    }
+
+    Ok(results)
+}
+
+/// Builds a `SpotifyTrack` out of a raw track JSON object, as returned by
+/// both the search and track/album/playlist endpoints.
+fn spotify_track_from_json(i: &Value) -> SpotifyTrack {
+    SpotifyTrack {
         id: i["id"].as_str().unwrap_or("").into(),
         name: i["name"].as_str().unwrap_or("").into(),
         artists: i["artists"].as_array().unwrap_or(&vec![])
@@ -246,12 +558,176 @@ let results = items
             .as_str()
             .unwrap_or("")
             .into(),
-    })
-    .collect();
+    }
+}
+
+/// ======= SPOTIFY URL RESOLUTION ========
+/// Follows a paging object's `next` URL until exhausted, accumulating every
+/// page's `items` on top of the ones already fetched.
+async fn collect_paged_items(
+    token: &str,
+    mut next_url: Option<String>,
+    mut items: Vec<Value>,
+) -> Result<Vec<Value>, AudioStreamError> {
+    while let Some(url) = next_url {
+        let req = reqwest::Client::new()
+            .get(url)
+            .header(AUTHORIZATION, format!("Bearer {}", token));
+
+        let json = send_spotify_request(&req).await?;
+        let page_items = json["items"]
+            .as_array()
+            .map(|arr| arr.to_owned())
+            .unwrap_or_default();
+
+        items.extend(page_items);
+        next_url = json["next"].as_str().map(|s| s.to_string());
+    }
+
+    Ok(items)
+}
+
+async fn get_spotify_track(
+    token: &str,
+    id: &str,
+) -> Result<SpotifyTrack, AudioStreamError> {
+    let url = format!("https://api.spotify.com/v1/tracks/{}", id);
+
+    let req = reqwest::Client::new()
+        .get(url)
+        .header(AUTHORIZATION, format!("Bearer {}", token));
+
+    let json = send_spotify_request(&req).await?;
+    Ok(spotify_track_from_json(&json))
+}
+
+/// Fetches every track on an album, following pagination — `GET
+/// /v1/albums/{id}` only returns the first page (max 50 even if more are
+/// requested), so a deluxe/compilation release over that size would
+/// otherwise come back truncated.
+async fn get_spotify_album_tracks(
+    token: &str,
+    id: &str,
+) -> Result<Vec<SpotifyTrack>, AudioStreamError> {
+    let url = format!("https://api.spotify.com/v1/albums/{}", id);
+
+    let req = reqwest::Client::new()
+        .get(url)
+        .header(AUTHORIZATION, format!("Bearer {}", token));
+
+    let json = send_spotify_request(&req).await?;
+    let artwork = json["images"][0]["url"].as_str().unwrap_or("").to_string();
+
+    let first_page = json["tracks"]["items"]
+        .as_array()
+        .map(|arr| arr.to_owned())
+        .unwrap_or_default();
+    let next_url = json["tracks"]["next"].as_str().map(|s| s.to_string());
+
+    let items = collect_paged_items(token, next_url, first_page).await?;
+
+    let results = items
+        .iter()
+        .map(|i| {
+            let mut track = spotify_track_from_json(i);
+            if track.artwork.is_empty() {
+                track.artwork = artwork.clone();
+            }
+            track
+        })
+        .collect();
+
+    Ok(results)
+}
+
+/// Fetches every track on a playlist, following pagination — `GET
+/// /v1/playlists/{id}/tracks` defaults to a 100-track page, so a playlist
+/// over that size would otherwise come back truncated.
+async fn get_spotify_playlist_tracks(
+    token: &str,
+    id: &str,
+) -> Result<Vec<SpotifyTrack>, AudioStreamError> {
+    let url = format!("https://api.spotify.com/v1/playlists/{}/tracks", id);
+
+    let req = reqwest::Client::new()
+        .get(url)
+        .header(AUTHORIZATION, format!("Bearer {}", token));
+
+    let json = send_spotify_request(&req).await?;
+    let first_page = json["items"]
+        .as_array()
+        .map(|arr| arr.to_owned())
+        .unwrap_or_default();
+    let next_url = json["next"].as_str().map(|s| s.to_string());
+
+    let items = collect_paged_items(token, next_url, first_page).await?;
+
+    let results = items
+        .iter()
+        .map(|i| spotify_track_from_json(&i["track"]))
+        .collect();
 
     Ok(results)
 }
 
+#[derive(Deserialize)]
+struct ResolveRequest {
+    url: String,
+}
+
+#[derive(Serialize)]
+struct ResolvedTrack {
+    spotify_track: SpotifyTrack,
+    youtube_id: String,
+}
+
+/// Resolves a Spotify share URL to playable YouTube IDs: a track URL yields
+/// a single result, album/playlist URLs yield the full track list so a
+/// client can queue them.
+async fn resolve_spotify_url(
+    State(state): State<AppState>,
+    Json(body): Json<ResolveRequest>,
+) -> Result<Json<Vec<ResolvedTrack>>, AudioStreamError> {
+    dotenv().ok();
+
+    let spotify_id: SpotifyId = body
+        .url
+        .parse()
+        .map_err(|e: spotify_id::ParseSpotifyIdError| AudioStreamError::InvalidSpotifyId(e.to_string()))?;
+
+    let id = env::var("SPOTIFY_CLIENT_ID")
+        .map_err(|_| AudioStreamError::EnvVarError("SPOTIFY_CLIENT_ID".into()))?;
+    let secret = env::var("SPOTIFY_CLIENT_SECRET")
+        .map_err(|_| AudioStreamError::EnvVarError("SPOTIFY_CLIENT_SECRET".into()))?;
+
+    let token = get_cached_spotify_token(&state, &id, &secret).await?;
+
+    let tracks = match spotify_id {
+        SpotifyId::Track(track_id) => vec![get_spotify_track(&token, &track_id).await?],
+        SpotifyId::Album(album_id) => get_spotify_album_tracks(&token, &album_id).await?,
+        SpotifyId::Playlist(playlist_id) => {
+            get_spotify_playlist_tracks(&token, &playlist_id).await?
+        }
+        SpotifyId::Artist(_) => {
+            return Err(AudioStreamError::InvalidSpotifyId(
+                "Artist URLs are not resolvable to a track list".into(),
+            ))
+        }
+    };
+
+    let mut resolved = Vec::with_capacity(tracks.len());
+    for track in tracks {
+        let search_query = format!("{} {}", track.name, track.artists.join(" "));
+        let youtube_id = find_youtube_id(&search_query).await?;
+        resolved.push(ResolvedTrack {
+            spotify_track: track,
+            youtube_id,
+        });
+    }
+
+    Ok(Json(resolved))
+}
+
 /// ======= MAIN ========
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -265,12 +741,16 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         .allow_methods(vec![Method::GET, Method::POST]) // ✅ vec pour éviter l'erreur
         .allow_headers(Any);
 
+    let state = AppState::new();
+
     let app = Router::new()
         .route("/youtube/:youtube_id", get(stream_youtube))
         .route("/stream/:youtube_id", get(stream_youtube))
         .route("/yt/search", get(yt_search))       // ✅ route ajoutée
         .route("/spotify/search", post(spotify_search))
-        .layer(cors.clone());
+        .route("/resolve", post(resolve_spotify_url))
+        .layer(cors.clone())
+        .with_state(state);
 
     let port = std::env::var("PORT")
     .unwrap_or_else(|_| "3000".to_string())
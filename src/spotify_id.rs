@@ -0,0 +1,105 @@
+//! Typed Spotify ID/URI parsing.
+//!
+//! Every code path that touches a Spotify identifier (search, the `/resolve`
+//! endpoint, ...) should go through [`SpotifyId`] instead of re-deriving the
+//! resource kind and ID from a raw string, so malformed IDs are rejected in
+//! one place with a precise error.
+
+use std::fmt;
+use std::str::FromStr;
+
+/// A validated Spotify identifier, grouped by resource kind.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SpotifyId {
+    Track(String),
+    Album(String),
+    Playlist(String),
+    Artist(String),
+}
+
+/// True when `input` looks like a Spotify URI or share URL (`spotify:...`
+/// or `open.spotify.com/...`), as opposed to plain text that merely happens
+/// to have the shape of a bare ID. Callers that also accept free-text
+/// search terms should gate ID parsing on this, since a 22-character
+/// alphanumeric search query (a plausible band/track name) would otherwise
+/// be silently reinterpreted as a literal Spotify ID.
+pub fn looks_like_spotify_reference(input: &str) -> bool {
+    let input = input.trim();
+    input.starts_with("spotify:") || input.contains("open.spotify.com")
+}
+
+#[derive(Debug, Clone)]
+pub struct ParseSpotifyIdError(pub String);
+
+impl fmt::Display for ParseSpotifyIdError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid Spotify ID: {}", self.0)
+    }
+}
+
+impl std::error::Error for ParseSpotifyIdError {}
+
+/// Spotify resource IDs are 22-character base-62 strings.
+fn is_valid_id(id: &str) -> bool {
+    id.len() == 22 && id.chars().all(|c| c.is_ascii_alphanumeric())
+}
+
+fn build(kind: &str, id: &str) -> Result<SpotifyId, ParseSpotifyIdError> {
+    if !is_valid_id(id) {
+        return Err(ParseSpotifyIdError(id.to_string()));
+    }
+    match kind {
+        "track" => Ok(SpotifyId::Track(id.to_string())),
+        "album" => Ok(SpotifyId::Album(id.to_string())),
+        "playlist" => Ok(SpotifyId::Playlist(id.to_string())),
+        "artist" => Ok(SpotifyId::Artist(id.to_string())),
+        other => Err(ParseSpotifyIdError(format!(
+            "unknown Spotify resource type `{}`",
+            other
+        ))),
+    }
+}
+
+impl FromStr for SpotifyId {
+    type Err = ParseSpotifyIdError;
+
+    /// Accepts `spotify:track:<id>`-style URIs, `open.spotify.com/track/<id>`
+    /// share URLs (with or without query string), and bare IDs, which are
+    /// assumed to refer to a track.
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let input = input.trim();
+
+        if let Some(rest) = input.strip_prefix("spotify:") {
+            let mut parts = rest.splitn(2, ':');
+            let kind = parts.next().unwrap_or("");
+            let id = parts.next().unwrap_or("");
+            return build(kind, id);
+        }
+
+        if let Some(idx) = input.find("open.spotify.com") {
+            let path = &input[idx + "open.spotify.com".len()..];
+            let path = path.split(['?', '#']).next().unwrap_or(path);
+            let segments: Vec<&str> = path
+                .trim_matches('/')
+                .split('/')
+                .filter(|s| !s.is_empty())
+                .collect();
+
+            // The last two path segments are always `{kind}/{id}` — take
+            // those regardless of what precedes them (e.g. the `intl-en`
+            // locale segment Spotify now prefixes onto share URLs).
+            if segments.len() < 2 {
+                return Err(ParseSpotifyIdError(input.to_string()));
+            }
+            let id = segments[segments.len() - 1];
+            let kind = segments[segments.len() - 2];
+            return build(kind, id);
+        }
+
+        if is_valid_id(input) {
+            return Ok(SpotifyId::Track(input.to_string()));
+        }
+
+        Err(ParseSpotifyIdError(input.to_string()))
+    }
+}